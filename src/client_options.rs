@@ -0,0 +1,132 @@
+//! Builds [`MqttOptions`] with a client id of the form
+//! `hostname@pid#sequence`, which stays unique across several instances
+//! of this router run on the same host (and across multiple clients
+//! started in the same process) without needing broker-side coordination.
+
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use gethostname::gethostname;
+use rumqttc::v5::MqttOptions;
+use rumqttc::{TlsConfiguration, Transport};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+static CLIENT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Generate a client id of the form `hostname@pid#sequence`.
+fn generate_client_id() -> String {
+    let hostname = gethostname().to_string_lossy().into_owned();
+    let pid = process::id();
+    let sequence = CLIENT_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+
+    format!("{hostname}@{pid}#{sequence}")
+}
+
+/// Builder for [`MqttOptions`] covering the pieces a real broker
+/// connection needs beyond host/port: a generated client id, keep-alive,
+/// clean session, credentials, and TLS root certificates.
+pub struct MqttClientOptionsBuilder {
+    host: String,
+    port: u16,
+    keep_alive: Duration,
+    clean_start: bool,
+    credentials: Option<(String, String)>,
+    root_cert_store: Option<RootCertStore>,
+}
+
+impl MqttClientOptionsBuilder {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            keep_alive: Duration::from_secs(5),
+            clean_start: true,
+            credentials: None,
+            root_cert_store: None,
+        }
+    }
+
+    pub fn keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    pub fn clean_start(mut self, clean_start: bool) -> Self {
+        self.clean_start = clean_start;
+        self
+    }
+
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Enable TLS, trusting only the certificates in `store`.
+    pub fn root_cert_store(mut self, store: RootCertStore) -> Self {
+        self.root_cert_store = Some(store);
+        self
+    }
+
+    pub fn build(self) -> MqttOptions {
+        let mut options = MqttOptions::new(generate_client_id(), self.host, self.port);
+
+        options.set_keep_alive(self.keep_alive);
+        options.set_clean_start(self.clean_start);
+
+        if let Some((username, password)) = self.credentials {
+            options.set_credentials(username, password);
+        }
+
+        if let Some(store) = self.root_cert_store {
+            let tls_config = ClientConfig::builder()
+                .with_root_certificates(store)
+                .with_no_client_auth();
+
+            options.set_transport(Transport::Tls(TlsConfiguration::Rustls(Arc::new(tls_config))));
+        }
+
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_id_is_unique_and_has_expected_format() {
+        let first = generate_client_id();
+        let second = generate_client_id();
+
+        assert_ne!(first, second);
+
+        for id in [&first, &second] {
+            let (hostname, rest) = id.split_once('@').expect("id should contain '@'");
+            assert!(!hostname.is_empty());
+            assert!(rest.contains('#'));
+        }
+    }
+
+    #[test]
+    fn build_applies_keep_alive_and_clean_start() {
+        let options = MqttClientOptionsBuilder::new("localhost", 1883)
+            .keep_alive(Duration::from_secs(42))
+            .clean_start(false)
+            .build();
+
+        assert_eq!(options.keep_alive(), Duration::from_secs(42));
+        assert!(!options.clean_start());
+        assert_eq!(options.credentials(), None);
+    }
+
+    #[test]
+    fn build_applies_credentials() {
+        let options = MqttClientOptionsBuilder::new("localhost", 1883)
+            .credentials("user", "pass")
+            .build();
+
+        assert_eq!(options.credentials(), Some(("user".to_string(), "pass".to_string())));
+    }
+}
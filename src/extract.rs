@@ -0,0 +1,128 @@
+//! Typed payload extraction for [`MqttRequest`], so a handler can declare
+//! the concrete payload type it wants instead of parsing `Bytes` by hand.
+
+use std::fmt;
+
+use rumqttc::v5::mqttbytes::QoS;
+use rumqttc::v5::AsyncClient;
+use serde::de::DeserializeOwned;
+
+use crate::request::MqttRequest;
+
+/// Error produced when extracting a typed payload from an [`MqttRequest`]
+/// fails.
+#[derive(Debug)]
+pub enum ExtractError {
+    EmptyPayload,
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::EmptyPayload => write!(f, "payload is empty"),
+            ExtractError::Json(e) => write!(f, "malformed JSON payload: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExtractError::EmptyPayload => None,
+            ExtractError::Json(e) => Some(e),
+        }
+    }
+}
+
+/// Extracts `Self` from an [`MqttRequest`]. Implement this for any type a
+/// handler wants to pull straight out of the request instead of reading
+/// `req.payload` itself.
+pub trait FromRequest: Sized {
+    fn from_request(req: &MqttRequest) -> Result<Self, ExtractError>;
+}
+
+/// A payload deserialized as JSON into `T`.
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Json<T> {
+    fn from_request(req: &MqttRequest) -> Result<Self, ExtractError> {
+        if req.payload.is_empty() {
+            return Err(ExtractError::EmptyPayload);
+        }
+
+        serde_json::from_slice(&req.payload)
+            .map(Json)
+            .map_err(ExtractError::Json)
+    }
+}
+
+/// Log `err` and, if `error_topic` is set, republish a small JSON
+/// description of it there so extraction failures are observable without
+/// tailing logs.
+pub async fn report_extract_error(client: &AsyncClient, error_topic: Option<&str>, source_topic: &str, err: &ExtractError) {
+    log::warn!("Failed to extract payload for topic {source_topic:?}: {err}");
+
+    if let Some(error_topic) = error_topic {
+        let body = serde_json::json!({
+            "topic": source_topic,
+            "error": err.to_string(),
+        });
+
+        if let Err(e) = client.publish(error_topic, QoS::AtLeastOnce, false, body.to_string()).await {
+            log::warn!("Failed to publish extraction error to {error_topic:?}: {e:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rumqttc::v5::{AsyncClient, MqttOptions};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Command {
+        action: String,
+    }
+
+    fn test_request(payload: &[u8]) -> MqttRequest {
+        let (client, _eventloop) = AsyncClient::new(MqttOptions::new("test", "localhost", 1883), 1);
+
+        MqttRequest {
+            topic: "devices/kitchen/command".to_string(),
+            payload: Bytes::copy_from_slice(payload),
+            qos: QoS::AtMostOnce,
+            retain: false,
+            captures: vec!["kitchen".to_string()],
+            response_topic: None,
+            correlation_data: None,
+            default_response_topic: None,
+            client,
+        }
+    }
+
+    #[test]
+    fn empty_payload_is_rejected() {
+        let req = test_request(b"");
+
+        assert!(matches!(Json::<Command>::from_request(&req), Err(ExtractError::EmptyPayload)));
+    }
+
+    #[test]
+    fn malformed_json_is_rejected() {
+        let req = test_request(b"not json");
+
+        assert!(matches!(Json::<Command>::from_request(&req), Err(ExtractError::Json(_))));
+    }
+
+    #[test]
+    fn valid_json_is_extracted() {
+        let req = test_request(br#"{"action": "restart"}"#);
+
+        let Json(command) = Json::<Command>::from_request(&req).unwrap();
+        assert_eq!(command, Command { action: "restart".to_string() });
+    }
+}
@@ -2,85 +2,85 @@ use std::error::Error;
 use std::time::Duration;
 use std::sync::Arc;
 
-use tokio::{task,time};
-use rumqttc::{self, AsyncClient, MqttOptions, QoS, Event, Incoming, EventLoop};
+use rumqttc::v5::mqttbytes::QoS;
+use rumqttc::v5::AsyncClient;
 
 use async_trait::async_trait;
 
-use pretty_env_logger;
+mod topic_trie;
+mod router;
+mod request;
+mod extract;
+mod status;
+mod supervisor;
+mod client_options;
 
-use indexmap::IndexMap;
-use std::collections::HashMap;
+use router::{MqttHandler, MqttRouter};
+use request::MqttRequest;
+use extract::{FromRequest, Json, report_extract_error};
+use client_options::MqttClientOptionsBuilder;
+use tokio_rustls::rustls::RootCertStore;
 
-use log;
-
-
-// https://internals.rust-lang.org/t/allowing-calling-static-methods-through-trait-objects/10417/5
+#[derive(Clone, Copy)]
+struct HelloHandler;
 
 #[async_trait]
-pub trait MqttHandler {
-    async fn call_async(&self, client: AsyncClient);
-    fn path() -> &'static str where Self: Sized;
-    async fn subscribe(client: &mut AsyncClient) where Self: Sized;
-}
-
-pub struct MqttRouter {
-    routes: IndexMap<String, Box<dyn MqttHandler + Send + Sync>>,
-}
+impl MqttHandler for HelloHandler {
+    async fn call_async(&self, ctx: MqttRequest, _client: AsyncClient) {
+        println!("Hello handler! Payload = {:?}", ctx.payload);
 
-impl MqttRouter {
-    pub fn new() -> Self {
-        Self {
-            routes: IndexMap::new(),
+        if let Err(e) = ctx.reply("Hello back!".as_bytes()).await {
+            log::warn!("Failed to send hello reply: {e}");
         }
     }
 
-    pub async fn add_route<T>(&mut self, client: &mut AsyncClient, handler: T)
-    where
-        T: MqttHandler + Send + Sync + 'static,
-    {
-        let path = <T as MqttHandler>::path();
-
-        log::info!("Add route: {:?}", path);
-
-        self.routes.insert(String::from(path), Box::new(handler));
-        let _ = <T as MqttHandler>::subscribe(client).await;
+    fn path() -> &'static str {
+        "hello/world"
     }
 
-    pub async fn handle_request(&self, client: AsyncClient, path: String)
-    {
-        match self.routes.get(&path) {
-            Some(handler) => {
-                handler.call_async(client).await;
-            }
-
-            None => {
-                println!("No route found for path: {}", path);
-            }
-        }
+    async fn subscribe(client: &mut AsyncClient) {
+        log::debug!("Subscribe hello handler!");
+        client.subscribe(Self::path(), QoS::AtMostOnce).await.unwrap();
     }
 }
 
+#[derive(serde::Deserialize)]
+struct Command {
+    action: String,
+}
+
+/// Expects a JSON body like `{"action": "restart"}` on `devices/+/command`,
+/// using [`Json`] to extract it instead of parsing `ctx.payload` by hand.
+/// Malformed or empty payloads are reported to `devices/errors` via
+/// [`report_extract_error`] rather than crashing the handler task.
 #[derive(Clone, Copy)]
-struct HelloHandler;
+struct CommandHandler;
 
 #[async_trait]
-impl MqttHandler for HelloHandler {
-    async fn call_async(&self, client: AsyncClient) {
-        println!("Hello handler!");
+impl MqttHandler for CommandHandler {
+    async fn call_async(&self, ctx: MqttRequest, client: AsyncClient) {
+        match Json::<Command>::from_request(&ctx) {
+            Ok(Json(command)) => {
+                log::info!(
+                    "Command on {:?} (qos={:?}, retain={}, captures={:?}): {}",
+                    ctx.topic, ctx.qos, ctx.retain, ctx.captures, command.action
+                );
+
+                let _ = ctx.reply(format!("ok: {}", command.action).into_bytes()).await;
+            }
 
-        let resp = "Hello back!";
-        client.publish("hello/back", QoS::AtLeastOnce, false, resp.as_bytes())
-            .await
-            .unwrap();
+            Err(e) => {
+                report_extract_error(&client, Some("devices/errors"), &ctx.topic, &e).await;
+            }
+        }
     }
 
     fn path() -> &'static str {
-        "hello/world"
+        "devices/+/command"
     }
 
     async fn subscribe(client: &mut AsyncClient) {
-        log::debug!("Subscribe hello handler!");
+        log::debug!("Subscribe command handler!");
         client.subscribe(Self::path(), QoS::AtMostOnce).await.unwrap();
     }
 }
@@ -91,59 +91,53 @@ async fn main() -> Result<(), Box<dyn Error>> {
     pretty_env_logger::init();
     log::info!("Hello world!");
 
-    let mqttoptions = MqttOptions::new("mqtt-async-connector", "localhost", 1883);
-    let (mut client, mut eventloop) = AsyncClient::new(mqttoptions, 10);
-    let mut router = MqttRouter::new();
+    let mut options_builder = MqttClientOptionsBuilder::new("localhost", 1883)
+        .keep_alive(Duration::from_secs(5))
+        .clean_start(true);
 
-    router.add_route(&mut client, HelloHandler).await;
-    let router = Arc::new(router);
+    if let (Ok(username), Ok(password)) = (std::env::var("MQTT_USERNAME"), std::env::var("MQTT_PASSWORD")) {
+        options_builder = options_builder.credentials(username, password);
+    }
 
-    loop {
-        let event = eventloop.poll().await;
+    if std::env::var("MQTT_TLS").is_ok_and(|v| v == "1") {
+        // Starts from an empty store; populate it with your broker's CA
+        // before relying on this in a real deployment.
+        options_builder = options_builder.root_cert_store(RootCertStore::empty());
+    }
 
-        match &event {
-            Ok(v) => {
-                log::trace!("Event = {v:?}");
+    let mut mqttoptions = options_builder.build();
 
-                if let Event::Incoming(Incoming::Publish(pub_event)) = v {
-                    log::debug!("> Publish event!");
+    let mut router = MqttRouter::new()
+        .with_status_prefix("devices/mqtt-async-connector")
+        .with_default_response_topic("hello/back");
+    if let Some(status) = router.status() {
+        log::info!("Publishing presence on {:?}", status.topic());
+        status.configure_last_will(&mut mqttoptions);
+    }
 
-                    let new_client  = client.clone();
-                    let topic       = String::from(&pub_event.topic);
-                    let router_arc  = router.clone();
-                    tokio::spawn(async move {
-                        router_arc.handle_request(new_client, topic).await;
-                    });
-                }
-            }
+    let (mut client, eventloop) = AsyncClient::new(mqttoptions, 10);
 
-            Err(e) => {
-                println!("Error = {e:?}");
-                return Ok(());
+    router.add_route(&mut client, HelloHandler).await;
+    router.add_route(&mut client, CommandHandler).await;
+    let router = Arc::new(router);
+
+    tokio::select! {
+        result = supervisor::serve(router.clone(), eventloop, client.clone(), supervisor::default_fatal) => {
+            if let Err(e) = result {
+                log::error!("Connection supervisor gave up: {e:?}");
             }
         }
-    }
-}
 
-async fn hello_handler(client: AsyncClient) {
-    println!("Hello handler!");
+        _ = tokio::signal::ctrl_c() => {
+            log::info!("Shutdown requested");
 
-    let resp = "Hello back!";
-    client.publish("hello/back", QoS::AtLeastOnce, false, resp.as_bytes())
-        .await
-        .unwrap();
-}
-
-async fn handle(topic: String, client: AsyncClient) -> Result<(), ()> {
-    println!("Handle !");
-    match topic.as_str() {
-        "hello/world" => {
-            hello_handler(client).await;
-            Ok(())
-        },
-
-        _ => {
-            Err(())
+            if let Some(status) = router.status() {
+                if let Err(e) = status.publish_death(&client).await {
+                    log::warn!("Failed to publish stopped status: {e:?}");
+                }
+            }
         }
     }
+
+    Ok(())
 }
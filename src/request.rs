@@ -0,0 +1,71 @@
+use bytes::Bytes;
+use rumqttc::v5::mqttbytes::v5::PublishProperties;
+use rumqttc::v5::mqttbytes::QoS;
+use rumqttc::v5::{AsyncClient, ClientError};
+
+/// Error returned by [`MqttRequest::reply`].
+#[derive(Debug)]
+pub enum ReplyError {
+    /// The publish carried no `ResponseTopic` and the router has no
+    /// default response topic configured.
+    NoResponseTopic,
+    Client(Box<ClientError>),
+}
+
+impl std::fmt::Display for ReplyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplyError::NoResponseTopic => write!(f, "no response topic on the request and no default configured on the router"),
+            ReplyError::Client(e) => write!(f, "failed to publish reply: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReplyError::NoResponseTopic => None,
+            ReplyError::Client(e) => Some(e),
+        }
+    }
+}
+
+/// Context handed to a [`crate::router::MqttHandler`] for a single matched
+/// publish: the concrete topic it arrived on, the raw payload, its QoS and
+/// retain flag, whatever segments the matched filter's `+`/`#` wildcards
+/// captured (in filter order), and the v5 `ResponseTopic`/`CorrelationData`
+/// properties needed to reply to it.
+pub struct MqttRequest {
+    pub topic: String,
+    pub payload: Bytes,
+    pub qos: QoS,
+    pub retain: bool,
+    pub captures: Vec<String>,
+    pub response_topic: Option<String>,
+    pub correlation_data: Option<Bytes>,
+
+    pub(crate) default_response_topic: Option<String>,
+    pub(crate) client: AsyncClient,
+}
+
+impl MqttRequest {
+    /// Publish `payload` back to the caller: the request's own
+    /// `response_topic` if the publish carried one, otherwise the
+    /// router's configured default. Echoes back the request's
+    /// `correlation_data`, if any, so the caller can match the reply to
+    /// its request.
+    pub async fn reply(&self, payload: impl Into<Vec<u8>>) -> Result<(), ReplyError> {
+        let topic = self.response_topic.as_deref()
+            .or(self.default_response_topic.as_deref())
+            .ok_or(ReplyError::NoResponseTopic)?;
+
+        let properties = PublishProperties {
+            correlation_data: self.correlation_data.clone(),
+            ..Default::default()
+        };
+
+        self.client.publish_with_properties(topic, QoS::AtLeastOnce, false, payload.into(), properties)
+            .await
+            .map_err(|e| ReplyError::Client(Box::new(e)))
+    }
+}
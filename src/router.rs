@@ -0,0 +1,131 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use rumqttc::v5::{AsyncClient, mqttbytes::v5::Publish};
+
+use crate::request::MqttRequest;
+use crate::status::StatusLifecycle;
+use crate::topic_trie::TopicTrie;
+
+// https://internals.rust-lang.org/t/allowing-calling-static-methods-through-trait-objects/10417/5
+
+#[async_trait]
+pub trait MqttHandler {
+    async fn call_async(&self, ctx: MqttRequest, client: AsyncClient);
+    fn path() -> &'static str where Self: Sized;
+    async fn subscribe(client: &mut AsyncClient) where Self: Sized;
+}
+
+// `MqttHandler::subscribe` is a static method, so it can't be called
+// through a `dyn MqttHandler` trait object (see the link above). This
+// adapter captures the concrete `T` at registration time so the router
+// can still re-issue the subscription later, e.g. after a reconnect.
+#[async_trait]
+trait RouteSubscribe: Send + Sync {
+    async fn subscribe(&self, client: &mut AsyncClient);
+}
+
+struct RouteSubscribeFn<T>(PhantomData<fn() -> T>);
+
+#[async_trait]
+impl<T: MqttHandler + Send + Sync> RouteSubscribe for RouteSubscribeFn<T> {
+    async fn subscribe(&self, client: &mut AsyncClient) {
+        let _ = <T as MqttHandler>::subscribe(client).await;
+    }
+}
+
+pub struct MqttRouter {
+    routes: Vec<Box<dyn MqttHandler + Send + Sync>>,
+    subscriptions: Vec<Box<dyn RouteSubscribe>>,
+    trie: TopicTrie,
+    status: Option<StatusLifecycle>,
+    default_response_topic: Option<String>,
+}
+
+impl MqttRouter {
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            subscriptions: Vec::new(),
+            trie: TopicTrie::new(),
+            status: None,
+            default_response_topic: None,
+        }
+    }
+
+    /// Give this router a `<status_prefix>/status` presence topic: a
+    /// retained birth message once connected, a retained death message on
+    /// graceful shutdown, and a last will covering ungraceful disconnects.
+    pub fn with_status_prefix(mut self, status_prefix: impl Into<String>) -> Self {
+        self.status = Some(StatusLifecycle::new(status_prefix));
+        self
+    }
+
+    pub fn status(&self) -> Option<&StatusLifecycle> {
+        self.status.as_ref()
+    }
+
+    /// Fall back to this topic for [`MqttRequest::reply`] when a publish
+    /// carries no v5 `ResponseTopic` property.
+    pub fn with_default_response_topic(mut self, topic: impl Into<String>) -> Self {
+        self.default_response_topic = Some(topic.into());
+        self
+    }
+
+    pub async fn add_route<T>(&mut self, client: &mut AsyncClient, handler: T)
+    where
+        T: MqttHandler + Send + Sync + 'static,
+    {
+        let path = <T as MqttHandler>::path();
+
+        log::info!("Add route: {:?}", path);
+
+        let idx = self.routes.len();
+        self.routes.push(Box::new(handler));
+        self.subscriptions.push(Box::new(RouteSubscribeFn::<T>(PhantomData)));
+        self.trie.insert(path, idx);
+
+        let _ = <T as MqttHandler>::subscribe(client).await;
+    }
+
+    /// Re-issue every registered handler's subscription. Called after a
+    /// fresh `ConnAck` so subscriptions survive a reconnect.
+    pub async fn resubscribe(&self, client: &mut AsyncClient) {
+        for subscription in &self.subscriptions {
+            subscription.subscribe(client).await;
+        }
+    }
+
+    /// Dispatch an incoming publish to every registered handler whose
+    /// filter matches its topic (a topic can match more than one filter,
+    /// e.g. `devices/#` and `devices/+/status`).
+    pub async fn handle_request(&self, client: AsyncClient, publish: &Publish) {
+        let topic = String::from_utf8_lossy(&publish.topic).into_owned();
+        let matches = self.trie.matches(&topic);
+
+        if matches.is_empty() {
+            println!("No route found for path: {}", topic);
+            return;
+        }
+
+        let (response_topic, correlation_data) = publish.properties.as_ref()
+            .map(|properties| (properties.response_topic.clone(), properties.correlation_data.clone()))
+            .unwrap_or((None, None));
+
+        for (idx, captures) in matches {
+            let ctx = MqttRequest {
+                topic: topic.clone(),
+                payload: publish.payload.clone(),
+                qos: publish.qos,
+                retain: publish.retain,
+                captures,
+                response_topic: response_topic.clone(),
+                correlation_data: correlation_data.clone(),
+                default_response_topic: self.default_response_topic.clone(),
+                client: client.clone(),
+            };
+
+            self.routes[idx].call_async(ctx, client.clone()).await;
+        }
+    }
+}
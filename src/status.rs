@@ -0,0 +1,67 @@
+//! Birth/death presence lifecycle for an [`crate::router::MqttRouter`]:
+//! a retained `running` status is published once the connection is up,
+//! a retained `stopped` status is published on graceful shutdown, and an
+//! MQTT last will covers the ungraceful case.
+
+use rumqttc::v5::{AsyncClient, ClientError, MqttOptions};
+use rumqttc::v5::mqttbytes::v5::LastWill;
+use rumqttc::v5::mqttbytes::QoS;
+use serde::Serialize;
+
+/// Presence state published on a router's status topic.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Running,
+    Stopped,
+}
+
+#[derive(Serialize)]
+struct StatusPayload {
+    status: Status,
+}
+
+/// Owns a router's `<prefix>/status` topic and publishes retained presence
+/// messages to it.
+pub struct StatusLifecycle {
+    topic: String,
+}
+
+impl StatusLifecycle {
+    pub fn new(status_prefix: impl Into<String>) -> Self {
+        Self {
+            topic: format!("{}/status", status_prefix.into()),
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Configure `options`'s last will so a retained `stopped` status is
+    /// published by the broker if the connection drops without a clean
+    /// shutdown.
+    pub fn configure_last_will(&self, options: &mut MqttOptions) {
+        let payload = Self::payload(Status::Stopped);
+        options.set_last_will(LastWill::new(&self.topic, payload, QoS::AtLeastOnce, true, None));
+    }
+
+    /// Publish a retained `running` status. Call this once the connection's
+    /// `ConnAck` has come back.
+    pub async fn publish_birth(&self, client: &AsyncClient) -> Result<(), ClientError> {
+        self.publish(client, Status::Running).await
+    }
+
+    /// Publish a retained `stopped` status. Call this on graceful shutdown.
+    pub async fn publish_death(&self, client: &AsyncClient) -> Result<(), ClientError> {
+        self.publish(client, Status::Stopped).await
+    }
+
+    async fn publish(&self, client: &AsyncClient, status: Status) -> Result<(), ClientError> {
+        client.publish(&self.topic, QoS::AtLeastOnce, true, Self::payload(status)).await
+    }
+
+    fn payload(status: Status) -> Vec<u8> {
+        serde_json::to_vec(&StatusPayload { status }).expect("StatusPayload always serializes")
+    }
+}
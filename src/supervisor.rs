@@ -0,0 +1,90 @@
+//! Connection supervisor: keeps the event loop alive across transient
+//! broker disconnects instead of letting a single `ConnectionError` kill
+//! the whole client. Retries use capped exponential backoff with jitter,
+//! and [`serve`] only gives up once the caller's `fatal` predicate says
+//! an error isn't worth retrying.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rumqttc::v5::{AsyncClient, ConnectionError, Event, EventLoop, Incoming};
+
+use crate::router::MqttRouter;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The default `fatal` predicate for [`serve`]: `true` for a
+/// [`ConnectionError`] that retrying won't fix (bad credentials, protocol
+/// mismatch).
+pub fn default_fatal(err: &ConnectionError) -> bool {
+    matches!(err, ConnectionError::ConnectionRefused(_) | ConnectionError::NotConnAck(_))
+}
+
+/// Run `router` against `eventloop`/`client` until `fatal` reports a
+/// connection error as unrecoverable. Every other error is retried with
+/// capped exponential backoff plus jitter, and every handler's
+/// subscription is re-issued after each fresh `ConnAck` so subscriptions
+/// survive a reconnect.
+///
+/// `fatal` is caller-supplied rather than hardcoded so it can be widened
+/// (e.g. treating a refused connection as transient against a broker that
+/// bounces connections during a rolling restart) or narrowed per
+/// deployment.
+pub async fn serve(
+    router: Arc<MqttRouter>,
+    mut eventloop: EventLoop,
+    mut client: AsyncClient,
+    fatal: impl Fn(&ConnectionError) -> bool,
+) -> Result<(), ConnectionError> {
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        match eventloop.poll().await {
+            Ok(event) => {
+                backoff = INITIAL_BACKOFF;
+                log::trace!("Event = {event:?}");
+
+                match event {
+                    Event::Incoming(Incoming::ConnAck(_)) => {
+                        router.resubscribe(&mut client).await;
+
+                        if let Some(status) = router.status() {
+                            if let Err(e) = status.publish_birth(&client).await {
+                                log::warn!("Failed to publish running status: {e:?}");
+                            }
+                        }
+                    }
+
+                    Event::Incoming(Incoming::Publish(pub_event)) => {
+                        let new_client = client.clone();
+                        let router_arc = router.clone();
+                        tokio::spawn(async move {
+                            router_arc.handle_request(new_client, &pub_event).await;
+                        });
+                    }
+
+                    _ => {}
+                }
+            }
+
+            Err(e) if fatal(&e) => {
+                log::error!("Fatal connection error, giving up: {e:?}");
+                return Err(e);
+            }
+
+            Err(e) => {
+                log::warn!("Connection error, retrying in {backoff:?}: {e:?}");
+                tokio::time::sleep(backoff + jitter(backoff)).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// A small random fraction (0-25%) of `base`, so many reconnecting clients
+/// don't all retry in lockstep.
+fn jitter(base: Duration) -> Duration {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    base.mul_f64((nanos as f64 / 1_000_000_000.0) * 0.25)
+}
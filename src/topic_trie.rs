@@ -0,0 +1,175 @@
+//! Trie-based matching of MQTT topic filters (`+` / `#` wildcards) against
+//! concrete topics. Used by [`crate::router::MqttRouter`] to dispatch an
+//! incoming publish to every handler whose registered filter matches.
+
+use std::collections::HashMap;
+
+/// A node of the topic trie. Each level corresponds to one `/`-separated
+/// segment of a registered filter.
+#[derive(Default)]
+pub struct TopicTrie {
+    literal: HashMap<String, TopicTrie>,
+    plus: Option<Box<TopicTrie>>,
+    hash: Option<Box<TopicTrie>>,
+    handlers: Vec<usize>,
+}
+
+impl TopicTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert `handler_idx` under the given filter, e.g. `"sensors/+/temp"`
+    /// or `"devices/#"`.
+    pub fn insert(&mut self, filter: &str, handler_idx: usize) {
+        let segments: Vec<&str> = filter.split('/').collect();
+        self.insert_segments(&segments, handler_idx);
+    }
+
+    fn insert_segments(&mut self, segments: &[&str], handler_idx: usize) {
+        match segments.split_first() {
+            None => self.handlers.push(handler_idx),
+
+            Some((&"#", rest)) => {
+                debug_assert!(rest.is_empty(), "'#' is only valid as the last segment of a filter");
+                self.hash.get_or_insert_with(Default::default).handlers.push(handler_idx);
+            }
+
+            Some((&"+", rest)) => {
+                self.plus.get_or_insert_with(Default::default).insert_segments(rest, handler_idx);
+            }
+
+            Some((seg, rest)) => {
+                self.literal.entry((*seg).to_string()).or_default().insert_segments(rest, handler_idx);
+            }
+        }
+    }
+
+    /// Return, for every handler whose filter matches `topic`, its index
+    /// together with the segments its `+`/`#` wildcards captured, in
+    /// filter order. A terminal `#` captures the remainder of the topic
+    /// joined back together with `/` (possibly empty). `+` matches exactly
+    /// one non-empty level: an empty level (e.g. adjacent slashes in the
+    /// topic) never matches a `+`.
+    pub fn matches(&self, topic: &str) -> Vec<(usize, Vec<String>)> {
+        let segments: Vec<&str> = topic.split('/').collect();
+        let mut out = Vec::new();
+        let mut captures = Vec::new();
+        self.collect_matches(&segments, &mut captures, &mut out);
+        out
+    }
+
+    fn collect_matches(&self, segments: &[&str], captures: &mut Vec<String>, out: &mut Vec<(usize, Vec<String>)>) {
+        // A '#' child matches the rest of the topic, including zero
+        // remaining levels, so it's checked before descending further.
+        if let Some(hash) = &self.hash {
+            for &idx in &hash.handlers {
+                let mut caps = captures.clone();
+                caps.push(segments.join("/"));
+                out.push((idx, caps));
+            }
+        }
+
+        match segments.split_first() {
+            None => {
+                for &idx in &self.handlers {
+                    out.push((idx, captures.clone()));
+                }
+            }
+
+            Some((seg, rest)) => {
+                if let Some(child) = self.literal.get(*seg) {
+                    child.collect_matches(rest, captures, out);
+                }
+
+                if let Some(plus) = &self.plus {
+                    if !seg.is_empty() {
+                        captures.push((*seg).to_string());
+                        plus.collect_matches(rest, captures, out);
+                        captures.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_literal_filter() {
+        let mut trie = TopicTrie::new();
+        trie.insert("sensors/temp", 0);
+
+        assert_eq!(trie.matches("sensors/temp"), vec![(0, vec![])]);
+        assert_eq!(trie.matches("sensors/humidity"), vec![]);
+    }
+
+    #[test]
+    fn plus_captures_single_segment() {
+        let mut trie = TopicTrie::new();
+        trie.insert("sensors/+/temp", 0);
+
+        assert_eq!(trie.matches("sensors/kitchen/temp"), vec![(0, vec!["kitchen".to_string()])]);
+        assert_eq!(trie.matches("sensors/kitchen/bathroom/temp"), vec![]);
+    }
+
+    #[test]
+    fn plus_does_not_match_empty_segment() {
+        let mut trie = TopicTrie::new();
+        trie.insert("sensors/+/temp", 0);
+
+        assert_eq!(trie.matches("sensors//temp"), vec![]);
+    }
+
+    #[test]
+    fn hash_captures_remaining_path() {
+        let mut trie = TopicTrie::new();
+        trie.insert("devices/#", 0);
+
+        assert_eq!(
+            trie.matches("devices/kitchen/sensor/temp"),
+            vec![(0, vec!["kitchen/sensor/temp".to_string()])]
+        );
+    }
+
+    #[test]
+    fn hash_matches_zero_remaining_segments() {
+        let mut trie = TopicTrie::new();
+        trie.insert("devices/#", 0);
+
+        assert_eq!(trie.matches("devices"), vec![(0, vec![String::new()])]);
+    }
+
+    #[test]
+    fn overlapping_filters_all_match() {
+        let mut trie = TopicTrie::new();
+        trie.insert("devices/#", 0);
+        trie.insert("devices/+/status", 1);
+
+        let mut matches = trie.matches("devices/kitchen/status");
+        matches.sort_by_key(|(idx, _)| *idx);
+
+        assert_eq!(
+            matches,
+            vec![
+                (0, vec!["kitchen/status".to_string()]),
+                (1, vec!["kitchen".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_handlers_on_same_literal_filter_both_match() {
+        let mut trie = TopicTrie::new();
+        trie.insert("devices/status", 0);
+        trie.insert("devices/status", 1);
+
+        let mut matches = trie.matches("devices/status");
+        matches.sort_by_key(|(idx, _)| *idx);
+
+        assert_eq!(matches, vec![(0, vec![]), (1, vec![])]);
+    }
+}